@@ -12,13 +12,24 @@ use std::io::{Error, ErrorKind, Result};
 use mime::Mime;
 use url::Url;
 
+mod cache;
+mod data_url;
+mod error;
 mod file;
 pub(crate) mod image;
+mod lenient;
+mod mime_sniff;
+mod policy;
 
 pub(crate) mod svg;
 
 pub(crate) use self::image::InlineImageProtocol;
+pub use cache::CachingResourceHandler;
+pub use data_url::DataUrlResourceHandler;
+pub use error::ResourceError;
 pub use file::FileResourceHandler;
+pub use lenient::{LenientResourceHandler, ResourceOutcome};
+pub use policy::{PolicyResourceHandler, ResourceAccessPolicy};
 
 /// Data of a resource with associated mime type.
 #[derive(Debug, Clone)]
@@ -36,6 +47,18 @@ impl MimeData {
     pub fn mime_type_essence(&self) -> Option<&str> {
         self.mime_type.as_ref().map(|m| m.essence_str())
     }
+
+    /// Fill in `mime_type` by sniffing `data`, if `mime_type` is not already known.
+    ///
+    /// Inspects the leading bytes of `data` to recognise common image formats, so that resource
+    /// handlers which cannot determine a mime type from the URL alone (such as
+    /// [`FileResourceHandler`] for files without a recognised extension) still let the inline
+    /// image protocol decide how to render the resource.
+    pub fn sniff_mime_type(&mut self) {
+        if self.mime_type.is_none() {
+            self.mime_type = mime_sniff::sniff(&self.data);
+        }
+    }
 }
 
 /// Handle resource URLs.
@@ -103,7 +126,10 @@ impl ResourceUrlHandler for DispatchingResourceHandler {
     fn read_resource(&self, url: &Url) -> Result<MimeData> {
         for handler in &self.handlers {
             match handler.read_resource(url) {
-                Ok(data) => return Ok(data),
+                Ok(mut data) => {
+                    data.sniff_mime_type();
+                    return Ok(data);
+                }
                 Err(error) if error.kind() == ErrorKind::Unsupported => continue,
                 Err(error) => return Err(error),
             }
@@ -0,0 +1,186 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Cache resources in memory, within size and total-memory bounds.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Mutex;
+
+use lru::LruCache;
+use url::Url;
+
+use super::{MimeData, ResourceUrlHandler};
+
+/// A resource handler which caches resources read from an inner handler.
+///
+/// Repeated reads of the same `url` are served from an in-memory LRU cache without hitting
+/// `inner` again. Two bounds protect against unbounded memory use: `max_resource_size` rejects
+/// any single resource larger than the limit before it ever enters the cache, and
+/// `max_total_cache_bytes` evicts the least recently used entries once the cache as a whole
+/// would exceed it.
+pub struct CachingResourceHandler<H> {
+    inner: H,
+    max_resource_size: u64,
+    max_total_cache_bytes: u64,
+    cache: Mutex<Cache>,
+}
+
+struct Cache {
+    entries: LruCache<Url, MimeData>,
+    total_bytes: u64,
+}
+
+impl<H> CachingResourceHandler<H> {
+    /// Wrap `inner`, caching its results up to `max_total_cache_bytes` in total, and refusing to
+    /// cache (or return) any single resource larger than `max_resource_size`.
+    pub fn new(inner: H, max_resource_size: u64, max_total_cache_bytes: u64) -> Self {
+        Self {
+            inner,
+            max_resource_size,
+            max_total_cache_bytes,
+            cache: Mutex::new(Cache {
+                entries: LruCache::unbounded(),
+                total_bytes: 0,
+            }),
+        }
+    }
+}
+
+impl Cache {
+    /// Insert `data` for `url`, evicting the least recently used entries until the cache fits
+    /// within `max_total_cache_bytes`.
+    fn insert(&mut self, url: Url, data: MimeData, size: u64, max_total_cache_bytes: u64) {
+        if let Some(evicted) = self.entries.put(url, data) {
+            self.total_bytes -= evicted.data.len() as u64;
+        }
+        self.total_bytes += size;
+        while self.total_bytes > max_total_cache_bytes {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.total_bytes -= evicted.data.len() as u64,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<H: ResourceUrlHandler> ResourceUrlHandler for CachingResourceHandler<H> {
+    /// Read `url`, from the cache if present, otherwise from the inner handler.
+    ///
+    /// Fail with an IO error if the inner handler returns more than `max_resource_size` bytes.
+    fn read_resource(&self, url: &Url) -> Result<MimeData> {
+        if let Some(data) = self.cache.lock().unwrap().entries.get(url) {
+            return Ok(data.clone());
+        }
+
+        let data = self.inner.read_resource(url)?;
+        let size = data.data.len() as u64;
+        if size > self.max_resource_size {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!(
+                    "Resource {url} is {size} bytes, exceeding the limit of {} bytes",
+                    self.max_resource_size
+                ),
+            ));
+        }
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(url.clone(), data.clone(), size, self.max_total_cache_bytes);
+        Ok(data)
+    }
+}
+
+impl<H: Debug> Debug for CachingResourceHandler<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingResourceHandler")
+            .field("inner", &self.inner)
+            .field("max_resource_size", &self.max_resource_size)
+            .field("max_total_cache_bytes", &self.max_total_cache_bytes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct MapHandler {
+        resources: HashMap<Url, Vec<u8>>,
+        reads: Mutex<u32>,
+    }
+
+    impl MapHandler {
+        fn new(resources: HashMap<Url, Vec<u8>>) -> Self {
+            Self {
+                resources,
+                reads: Mutex::new(0),
+            }
+        }
+    }
+
+    impl ResourceUrlHandler for MapHandler {
+        fn read_resource(&self, url: &Url) -> Result<MimeData> {
+            *self.reads.lock().unwrap() += 1;
+            self.resources
+                .get(url)
+                .cloned()
+                .map(|data| MimeData {
+                    mime_type: None,
+                    data,
+                })
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No resource for {url}")))
+        }
+    }
+
+    #[test]
+    fn repeated_reads_are_served_from_cache() {
+        let url = Url::parse("file:///a.png").unwrap();
+        let handler = MapHandler::new(HashMap::from([(url.clone(), vec![1, 2, 3])]));
+        let cache = CachingResourceHandler::new(handler, 1024, 1024);
+
+        cache.read_resource(&url).unwrap();
+        cache.read_resource(&url).unwrap();
+
+        assert_eq!(*cache.inner.reads.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn total_bytes_eviction_drops_least_recently_used() {
+        let url_a = Url::parse("file:///a.png").unwrap();
+        let url_b = Url::parse("file:///b.png").unwrap();
+        let handler = MapHandler::new(HashMap::from([
+            (url_a.clone(), vec![0u8; 10]),
+            (url_b.clone(), vec![0u8; 10]),
+        ]));
+        // Both resources together exceed the total cache bound, so reading `url_b` after
+        // `url_a` must evict `url_a`.
+        let cache = CachingResourceHandler::new(handler, 1024, 15);
+
+        cache.read_resource(&url_a).unwrap();
+        cache.read_resource(&url_b).unwrap();
+
+        let inner = cache.cache.lock().unwrap();
+        assert!(inner.entries.peek(&url_a).is_none());
+        assert!(inner.entries.peek(&url_b).is_some());
+        assert_eq!(inner.total_bytes, 10);
+    }
+
+    #[test]
+    fn resource_larger_than_limit_is_rejected() {
+        let url = Url::parse("file:///a.png").unwrap();
+        let handler = MapHandler::new(HashMap::from([(url.clone(), vec![0u8; 10])]));
+        let cache = CachingResourceHandler::new(handler, 5, 1024);
+
+        let error = cache.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::OutOfMemory);
+    }
+}
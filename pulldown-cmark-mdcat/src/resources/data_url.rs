@@ -0,0 +1,110 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Resource handler for `data:` URLs.
+
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+use base64::Engine;
+use mime::Mime;
+use url::Url;
+
+use super::{filter_schemes, MimeData, ResourceUrlHandler};
+
+/// The mime type a `data:` URL uses when it gives no media type, per RFC 2397.
+const DEFAULT_MIME_TYPE: &str = "text/plain;charset=US-ASCII";
+
+/// A resource handler for `data:` URLs.
+///
+/// Parses RFC 2397 data URLs of the form `data:[<mediatype>][;base64],<data>`, and decodes
+/// their payload directly, without touching the network or the file system.
+#[derive(Debug, Clone, Copy)]
+pub struct DataUrlResourceHandler;
+
+impl ResourceUrlHandler for DataUrlResourceHandler {
+    /// Read a `data:` URL.
+    ///
+    /// Return [`ErrorKind::Unsupported`] for any scheme other than `data`, and
+    /// [`ErrorKind::InvalidData`] if `url` is not a well-formed RFC 2397 data URL.
+    fn read_resource(&self, url: &Url) -> Result<MimeData> {
+        filter_schemes(&["data"], url)?;
+
+        let (meta, payload) = url.path().split_once(',').ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Data URL {url} has no comma separating media type from data"),
+            )
+        })?;
+
+        let is_base64 = meta.ends_with(";base64");
+        let media_type = meta.strip_suffix(";base64").unwrap_or(meta);
+        let media_type = if media_type.is_empty() {
+            DEFAULT_MIME_TYPE
+        } else {
+            media_type
+        };
+        let mime_type = Mime::from_str(media_type).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid media type in data URL {url}: {error}"),
+            )
+        })?;
+
+        let data = if is_base64 {
+            base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|error| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid base64 payload in data URL {url}: {error}"),
+                    )
+                })?
+        } else {
+            percent_encoding::percent_decode_str(payload).collect()
+        };
+
+        Ok(MimeData {
+            mime_type: Some(mime_type),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_payload_decodes() {
+        let url = Url::parse("data:image/png;base64,aGVsbG8=").unwrap();
+        let data = DataUrlResourceHandler.read_resource(&url).unwrap();
+        assert_eq!(data.data, b"hello");
+        assert_eq!(data.mime_type_essence(), Some("image/png"));
+    }
+
+    #[test]
+    fn percent_encoded_payload_decodes() {
+        let url = Url::parse("data:text/plain,hello%20world").unwrap();
+        let data = DataUrlResourceHandler.read_resource(&url).unwrap();
+        assert_eq!(data.data, b"hello world");
+        assert_eq!(data.mime_type_essence(), Some("text/plain"));
+    }
+
+    #[test]
+    fn missing_media_type_defaults_per_rfc_2397() {
+        let url = Url::parse("data:,hello").unwrap();
+        let data = DataUrlResourceHandler.read_resource(&url).unwrap();
+        assert_eq!(data.mime_type_essence(), Some("text/plain"));
+    }
+
+    #[test]
+    fn non_data_scheme_is_unsupported() {
+        let url = Url::parse("file:///tmp/a.png").unwrap();
+        let error = DataUrlResourceHandler.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
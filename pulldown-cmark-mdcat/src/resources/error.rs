@@ -0,0 +1,64 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A classified resource error, for callers which need to tell recoverable failures apart.
+
+use std::io::{Error, ErrorKind};
+
+use thiserror::Error;
+use url::Url;
+
+/// A resource read failure, classified by cause.
+///
+/// [`LenientResourceHandler`](super::LenientResourceHandler) builds this from the
+/// [`std::io::Error`] a [`ResourceUrlHandler`](super::ResourceUrlHandler) returns, so that
+/// callers can decide which classes of failure to tolerate and which to surface.
+#[derive(Debug, Error)]
+pub enum ResourceError {
+    /// No handler supports the scheme of this URL.
+    #[error("Unsupported scheme in {url}")]
+    UnsupportedScheme {
+        /// The URL whose scheme is unsupported.
+        url: Url,
+    },
+    /// The resource does not exist.
+    #[error("Resource not found: {url}")]
+    NotFound {
+        /// The URL which does not exist.
+        url: Url,
+    },
+    /// The resource exceeds a configured size limit.
+    #[error("Resource too large: {url}")]
+    TooLarge {
+        /// The URL whose resource was too large to read.
+        url: Url,
+    },
+    /// Reading the resource failed for any other reason, e.g. a network error or a decode
+    /// failure.
+    #[error("Failed to read {url}: {source}")]
+    Transport {
+        /// The URL which could not be read.
+        url: Url,
+        /// The underlying error.
+        #[source]
+        source: Error,
+    },
+}
+
+impl ResourceError {
+    /// Classify `error` which occurred while reading `url`.
+    pub(crate) fn classify(url: &Url, error: Error) -> Self {
+        match error.kind() {
+            ErrorKind::Unsupported => ResourceError::UnsupportedScheme { url: url.clone() },
+            ErrorKind::NotFound => ResourceError::NotFound { url: url.clone() },
+            ErrorKind::OutOfMemory => ResourceError::TooLarge { url: url.clone() },
+            _ => ResourceError::Transport {
+                url: url.clone(),
+                source: error,
+            },
+        }
+    }
+}
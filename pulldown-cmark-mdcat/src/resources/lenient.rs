@@ -0,0 +1,79 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tolerate recoverable resource read failures instead of aborting the render.
+
+use std::io::{Error, ErrorKind, Result};
+
+use url::Url;
+
+use super::error::ResourceError;
+use super::{MimeData, ResourceUrlHandler};
+
+/// The outcome of reading a resource through [`LenientResourceHandler::read_resource_leniently`].
+#[derive(Debug)]
+pub enum ResourceOutcome {
+    /// The resource was read successfully.
+    Available(MimeData),
+    /// The resource could not be read, but the failure is recoverable: the renderer should fall
+    /// back to the image's alt text/URL and continue with the rest of the document, rather than
+    /// aborting.
+    Unavailable(ResourceError),
+}
+
+/// A resource handler which tolerates recoverable read failures instead of propagating them.
+///
+/// Borrows paperoni's approach of ignoring failed image downloads and continuing to render the
+/// rest of the document. As a [`ResourceUrlHandler`], it downgrades recoverable failures to
+/// [`ErrorKind::Unsupported`] — the same error kind the renderer already tolerates for any
+/// other resource with no supporting handler — so plugging this wrapper in front of an inner
+/// handler is enough to make the renderer fall back to alt text instead of aborting.
+/// [`ErrorKind::PermissionDenied`] is not considered recoverable: it signals that
+/// [`PolicyResourceHandler`](super::PolicyResourceHandler) deliberately blocked the URL, and
+/// callers should still see that as a hard error rather than a silently skipped image.
+///
+/// Use [`read_resource_leniently`](Self::read_resource_leniently) directly instead when the
+/// caller wants to distinguish *why* a resource was unavailable, e.g. for logging.
+#[derive(Debug)]
+pub struct LenientResourceHandler<H> {
+    inner: H,
+}
+
+impl<H: ResourceUrlHandler> LenientResourceHandler<H> {
+    /// Wrap `inner`, tolerating its recoverable read failures.
+    pub fn new(inner: H) -> Self {
+        Self { inner }
+    }
+
+    /// Read `url`, converting recoverable failures into [`ResourceOutcome::Unavailable`].
+    ///
+    /// Still propagates [`ErrorKind::PermissionDenied`] errors, since those are deliberate
+    /// policy rejections rather than failures to tolerate.
+    pub fn read_resource_leniently(&self, url: &Url) -> Result<ResourceOutcome> {
+        match self.inner.read_resource(url) {
+            Ok(data) => Ok(ResourceOutcome::Available(data)),
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => Err(error),
+            Err(error) => Ok(ResourceOutcome::Unavailable(ResourceError::classify(
+                url, error,
+            ))),
+        }
+    }
+}
+
+impl<H: ResourceUrlHandler> ResourceUrlHandler for LenientResourceHandler<H> {
+    /// Read `url` through the inner handler, downgrading recoverable failures to
+    /// [`ErrorKind::Unsupported`] so the renderer falls back to alt text instead of aborting.
+    ///
+    /// Still propagates [`ErrorKind::PermissionDenied`], see the type-level docs.
+    fn read_resource(&self, url: &Url) -> Result<MimeData> {
+        match self.read_resource_leniently(url)? {
+            ResourceOutcome::Available(data) => Ok(data),
+            ResourceOutcome::Unavailable(error) => {
+                Err(Error::new(ErrorKind::Unsupported, error))
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Content-based mime type sniffing for resources without a known type.
+//!
+//! This mirrors the byte-signature classification of Servo's `mime_classifier`, restricted to
+//! the image formats mdcat actually renders.
+
+use mime::Mime;
+
+/// Guess the mime type of `data` from its leading bytes.
+///
+/// Recognises PNG, JPEG, GIF, WebP and SVG by their byte signatures, and returns `None` for
+/// anything else.
+pub fn sniff(data: &[u8]) -> Option<Mime> {
+    if data.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some(mime::IMAGE_PNG)
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(mime::IMAGE_JPEG)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(mime::IMAGE_GIF)
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        "image/webp".parse().ok()
+    } else if looks_like_svg(data) {
+        "image/svg+xml".parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Whether `data` looks like an SVG document, ignoring a leading BOM, whitespace, or XML
+/// declaration.
+fn looks_like_svg(data: &[u8]) -> bool {
+    let mut bytes = data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data);
+    bytes = trim_ascii_whitespace(bytes);
+    if let Some(rest) = strip_prefix_ignore_ascii_case(bytes, b"<?xml") {
+        match rest.iter().position(|&b| b == b'>') {
+            Some(end) => bytes = trim_ascii_whitespace(&rest[end + 1..]),
+            None => return false,
+        }
+    }
+    strip_prefix_ignore_ascii_case(bytes, b"<svg").is_some()
+}
+
+fn trim_ascii_whitespace(data: &[u8]) -> &[u8] {
+    let start = data.iter().position(|b| !b.is_ascii_whitespace());
+    match start {
+        Some(start) => &data[start..],
+        None => &[],
+    }
+}
+
+fn strip_prefix_ignore_ascii_case<'a>(data: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    if data.len() >= prefix.len() && data[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        Some(&data[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_png() {
+        let mut data = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        data.extend_from_slice(b"rest of file");
+        assert_eq!(sniff(&data).unwrap().essence_str(), "image/png");
+    }
+
+    #[test]
+    fn sniffs_jpeg() {
+        let mut data = vec![0xFF, 0xD8, 0xFF];
+        data.extend_from_slice(b"rest of file");
+        assert_eq!(sniff(&data).unwrap().essence_str(), "image/jpeg");
+    }
+
+    #[test]
+    fn sniffs_gif() {
+        assert_eq!(sniff(b"GIF87a...").unwrap().essence_str(), "image/gif");
+        assert_eq!(sniff(b"GIF89a...").unwrap().essence_str(), "image/gif");
+    }
+
+    #[test]
+    fn sniffs_webp() {
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(b"WEBPVP8 ");
+        assert_eq!(sniff(&data).unwrap().essence_str(), "image/webp");
+    }
+
+    #[test]
+    fn sniffs_svg_with_xml_declaration() {
+        let data = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>";
+        assert_eq!(sniff(data).unwrap().essence_str(), "image/svg+xml");
+    }
+
+    #[test]
+    fn sniffs_bare_svg() {
+        assert_eq!(
+            sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>")
+                .unwrap()
+                .essence_str(),
+            "image/svg+xml"
+        );
+    }
+
+    #[test]
+    fn unrecognised_data_is_not_sniffed() {
+        assert!(sniff(b"just some text").is_none());
+    }
+}
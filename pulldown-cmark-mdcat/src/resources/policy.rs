@@ -0,0 +1,237 @@
+// Copyright 2018-2020 Sebastian Wiesner <sebastian@swsnr.de>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Sandbox resource access behind a configurable policy.
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use url::Url;
+
+use super::{filter_schemes, MimeData, ResourceUrlHandler};
+
+/// A policy controlling which resource URLs [`PolicyResourceHandler`] allows through to its
+/// inner handler.
+///
+/// Modelled on librsvg's `AllowedUrl`: a resource is only read if it passes every check
+/// configured here, otherwise [`PolicyResourceHandler::read_resource`] fails: with
+/// [`ErrorKind::PermissionDenied`] if the policy rejects the URL, or with the original error if
+/// resolving a `file` URL against the file system fails (e.g. the path does not exist).
+#[derive(Debug, Clone)]
+pub struct ResourceAccessPolicy {
+    /// The URL schemes resources may use.
+    pub allowed_schemes: Vec<String>,
+    /// If set, the hosts URLs naming a remote host (e.g. `http://example.com`) may refer to;
+    /// such URLs with a host outside this set are denied. URLs with no host, such as `data:`
+    /// or `file:///path`, are unaffected.
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Whether to deny all URLs naming a remote host outright, regardless of `allowed_hosts`.
+    ///
+    /// URLs with no host, such as `data:` or `file:///path`, are local and unaffected.
+    pub forbid_remote: bool,
+    /// If set, the directory `file` URLs must resolve into.
+    ///
+    /// The resolved path is canonicalized before the check, so this also rejects `file` URLs
+    /// which escape the base directory through `..` or symlinks.
+    pub base_directory: Option<PathBuf>,
+}
+
+impl ResourceAccessPolicy {
+    /// Create a policy which only allows the given `schemes`, with no further restrictions.
+    pub fn allowing_schemes<I: IntoIterator<Item = S>, S: Into<String>>(schemes: I) -> Self {
+        Self {
+            allowed_schemes: schemes.into_iter().map(Into::into).collect(),
+            allowed_hosts: None,
+            forbid_remote: false,
+            base_directory: None,
+        }
+    }
+
+    /// Restrict remote URLs to the given `hosts`.
+    pub fn with_allowed_hosts<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        hosts: I,
+    ) -> Self {
+        self.allowed_hosts = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Deny all remote URLs, regardless of scheme or host.
+    pub fn forbidding_remote(mut self) -> Self {
+        self.forbid_remote = true;
+        self
+    }
+
+    /// Restrict `file` URLs to those resolving inside `directory`.
+    pub fn with_base_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.base_directory = Some(directory.into());
+        self
+    }
+
+    /// Check whether `url` is allowed by this policy.
+    ///
+    /// Returns [`ErrorKind::PermissionDenied`] when the policy itself rejects `url`. Failures
+    /// that occur while resolving `url` against the file system (e.g. the path does not exist)
+    /// are propagated with their original [`ErrorKind`] instead, so that callers such as
+    /// [`LenientResourceHandler`](super::LenientResourceHandler) can still tell a deliberate
+    /// policy rejection apart from an ordinary "file not found".
+    fn check(&self, url: &Url) -> Result<()> {
+        let schemes: Vec<&str> = self.allowed_schemes.iter().map(String::as_str).collect();
+        filter_schemes(&schemes, url).map_err(|error| {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                format!("Scheme not allowed for {url}: {error}"),
+            )
+        })?;
+
+        // `host_str()` is only `Some` for URLs that actually name a remote host to connect to
+        // (e.g. `http://example.com`); schemes like `data:` or `file:///path` carry no host and
+        // are local regardless of scheme, so they are exempt from the remote-access checks below.
+        if let Some(host) = url.host_str() {
+            if self.forbid_remote {
+                return Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!("Remote access is forbidden, rejecting {url}"),
+                ));
+            }
+            if let Some(allowed_hosts) = &self.allowed_hosts {
+                if !allowed_hosts.iter().any(|h| h == host) {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("Host not allowed for {url}"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(base_directory) = &self.base_directory {
+            if url.scheme() == "file" {
+                let path = url.to_file_path().map_err(|()| {
+                    Error::new(ErrorKind::PermissionDenied, format!("Invalid file URL {url}"))
+                })?;
+                let canonical_path = path.canonicalize()?;
+                let canonical_base = base_directory.canonicalize()?;
+                if !canonical_path.starts_with(&canonical_base) {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!(
+                            "{} escapes base directory {}",
+                            canonical_path.display(),
+                            canonical_base.display()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A resource handler which enforces a [`ResourceAccessPolicy`] before delegating to an inner
+/// handler.
+///
+/// This lets callers sandbox rendering of untrusted markdown, e.g. by forbidding remote access
+/// or confining `file` URLs to the directory of the document being rendered.
+#[derive(Debug)]
+pub struct PolicyResourceHandler<H> {
+    inner: H,
+    policy: ResourceAccessPolicy,
+}
+
+impl<H: ResourceUrlHandler> PolicyResourceHandler<H> {
+    /// Wrap `inner`, enforcing `policy` before every read.
+    pub fn new(inner: H, policy: ResourceAccessPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<H: ResourceUrlHandler> ResourceUrlHandler for PolicyResourceHandler<H> {
+    /// Read `url` through the inner handler, if `url` passes this handler's policy.
+    ///
+    /// Return an IO error with [`ErrorKind::PermissionDenied`] if the policy rejects `url`.
+    /// Unlike [`ErrorKind::Unsupported`], this error kind is not a signal to try another
+    /// handler, so [`DispatchingResourceHandler`](super::DispatchingResourceHandler) aborts
+    /// instead of silently falling through.
+    fn read_resource(&self, url: &Url) -> Result<MimeData> {
+        self.policy.check(url)?;
+        self.inner.read_resource(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn file_url_escaping_base_directory_is_denied() {
+        let root = std::env::temp_dir().join(format!(
+            "mdcat-policy-test-{}-{}",
+            std::process::id(),
+            "escape"
+        ));
+        let allowed = root.join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        let outside = root.join("outside.txt");
+        fs::write(&outside, b"secret").unwrap();
+
+        let policy = ResourceAccessPolicy::allowing_schemes(["file"]).with_base_directory(&allowed);
+        let url = Url::from_file_path(&outside).unwrap();
+
+        let error = policy.check(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn file_url_inside_base_directory_is_allowed() {
+        let root = std::env::temp_dir().join(format!(
+            "mdcat-policy-test-{}-{}",
+            std::process::id(),
+            "inside"
+        ));
+        fs::create_dir_all(&root).unwrap();
+        let inside = root.join("image.png");
+        fs::write(&inside, b"data").unwrap();
+
+        let policy = ResourceAccessPolicy::allowing_schemes(["file"]).with_base_directory(&root);
+        let url = Url::from_file_path(&inside).unwrap();
+
+        assert!(policy.check(&url).is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn data_url_is_not_treated_as_remote() {
+        let policy = ResourceAccessPolicy::allowing_schemes(["data"]).forbidding_remote();
+        let url = Url::parse("data:text/plain,hello").unwrap();
+
+        assert!(policy.check(&url).is_ok());
+    }
+
+    #[test]
+    fn remote_url_is_denied_when_forbidden() {
+        let policy = ResourceAccessPolicy::allowing_schemes(["http"]).forbidding_remote();
+        let url = Url::parse("http://example.com/image.png").unwrap();
+
+        let error = policy.check(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn remote_url_outside_allowed_hosts_is_denied() {
+        let policy =
+            ResourceAccessPolicy::allowing_schemes(["http"]).with_allowed_hosts(["example.com"]);
+        let url = Url::parse("http://evil.example/image.png").unwrap();
+
+        let error = policy.check(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    }
+}